@@ -0,0 +1,243 @@
+use evdev::Key;
+use std::{
+    collections::HashSet,
+    fmt, fs,
+    path::PathBuf,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Modifier {
+    Super,
+    Alt,
+    Control,
+    Shift,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hotkey {
+    pub keysym: Key,
+    pub modifiers: HashSet<Modifier>,
+    pub command: String,
+    pub modes: HashSet<String>,
+    pub enter_mode: Option<String>,
+    pub is_escape: bool,
+}
+
+impl Hotkey {
+    fn command(keysym: Key, modifiers: HashSet<Modifier>, command: String, modes: HashSet<String>) -> Hotkey {
+        Hotkey { keysym, modifiers, command, modes, enter_mode: None, is_escape: false }
+    }
+
+    fn enter_mode(
+        keysym: Key,
+        modifiers: HashSet<Modifier>,
+        target_mode: String,
+        modes: HashSet<String>,
+    ) -> Hotkey {
+        Hotkey { keysym, modifiers, command: String::new(), modes, enter_mode: Some(target_mode), is_escape: false }
+    }
+
+    fn escape(keysym: Key, modifiers: HashSet<Modifier>, modes: HashSet<String>) -> Hotkey {
+        Hotkey { keysym, modifiers, command: String::new(), modes, enter_mode: None, is_escape: true }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn load(path: PathBuf) -> Result<Vec<Hotkey>, ParseError> {
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| ParseError(format!("Unable to read {:#?}: {}", path, e)))?;
+    parse_contents(&contents)
+}
+
+// Bindings before the first `mode:<name>` line get an empty `modes` set, i.e. global.
+fn parse_contents(contents: &str) -> Result<Vec<Hotkey>, ParseError> {
+    let mut hotkeys = Vec::new();
+    let mut current_mode: Option<String> = None;
+
+    let mut lines = contents.lines().peekable();
+    while let Some(raw_line) = lines.next() {
+        let line = raw_line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(mode_name) = line.strip_prefix("mode:") {
+            current_mode = Some(mode_name.trim().to_string());
+            continue;
+        }
+
+        let command_line = match lines.next() {
+            Some(next) if !next.trim().is_empty() => next.trim(),
+            _ => {
+                return Err(ParseError(format!("Expected a command after binding: {}", line)));
+            }
+        };
+
+        let (modifiers, keysym) = parse_binding(line)?;
+        let modes: HashSet<String> = current_mode.clone().into_iter().collect();
+
+        let hotkey = if let Some(directive) = command_line.strip_prefix(':') {
+            let mut directive_parts = directive.splitn(2, char::is_whitespace);
+            match directive_parts.next().unwrap_or("") {
+                "mode" => {
+                    let target_mode = directive_parts
+                        .next()
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .ok_or_else(|| ParseError(format!("`:mode` requires a mode name: {}", line)))?;
+                    Hotkey::enter_mode(keysym, modifiers, target_mode.to_string(), modes)
+                }
+                "escape" => Hotkey::escape(keysym, modifiers, modes),
+                other => return Err(ParseError(format!("Unknown directive `:{}`: {}", other, line))),
+            }
+        } else {
+            Hotkey::command(keysym, modifiers, command_line.to_string(), modes)
+        };
+
+        hotkeys.push(hotkey);
+    }
+
+    Ok(hotkeys)
+}
+
+fn parse_binding(line: &str) -> Result<(HashSet<Modifier>, Key), ParseError> {
+    let mut tokens: Vec<&str> = line.split('+').map(str::trim).collect();
+    let keysym_token = tokens.pop().ok_or_else(|| ParseError(format!("Empty binding: {}", line)))?;
+
+    let mut modifiers = HashSet::new();
+    for token in tokens {
+        match parse_modifier(token) {
+            Some(modifier) => {
+                modifiers.insert(modifier);
+            }
+            None => return Err(ParseError(format!("Unknown modifier: {}", token))),
+        }
+    }
+
+    let keysym =
+        parse_keysym(keysym_token).ok_or_else(|| ParseError(format!("Unknown key: {}", keysym_token)))?;
+
+    Ok((modifiers, keysym))
+}
+
+fn parse_modifier(token: &str) -> Option<Modifier> {
+    match token.to_lowercase().as_str() {
+        "super" | "mod4" => Some(Modifier::Super),
+        "alt" | "mod1" => Some(Modifier::Alt),
+        "control" | "ctrl" => Some(Modifier::Control),
+        "shift" => Some(Modifier::Shift),
+        _ => None,
+    }
+}
+
+fn parse_keysym(token: &str) -> Option<Key> {
+    match token.to_lowercase().as_str() {
+        "a" => Some(Key::KEY_A),
+        "b" => Some(Key::KEY_B),
+        "c" => Some(Key::KEY_C),
+        "d" => Some(Key::KEY_D),
+        "e" => Some(Key::KEY_E),
+        "f" => Some(Key::KEY_F),
+        "g" => Some(Key::KEY_G),
+        "h" => Some(Key::KEY_H),
+        "i" => Some(Key::KEY_I),
+        "j" => Some(Key::KEY_J),
+        "k" => Some(Key::KEY_K),
+        "l" => Some(Key::KEY_L),
+        "m" => Some(Key::KEY_M),
+        "n" => Some(Key::KEY_N),
+        "o" => Some(Key::KEY_O),
+        "p" => Some(Key::KEY_P),
+        "q" => Some(Key::KEY_Q),
+        "r" => Some(Key::KEY_R),
+        "s" => Some(Key::KEY_S),
+        "t" => Some(Key::KEY_T),
+        "u" => Some(Key::KEY_U),
+        "v" => Some(Key::KEY_V),
+        "w" => Some(Key::KEY_W),
+        "x" => Some(Key::KEY_X),
+        "y" => Some(Key::KEY_Y),
+        "z" => Some(Key::KEY_Z),
+        "0" => Some(Key::KEY_0),
+        "1" => Some(Key::KEY_1),
+        "2" => Some(Key::KEY_2),
+        "3" => Some(Key::KEY_3),
+        "4" => Some(Key::KEY_4),
+        "5" => Some(Key::KEY_5),
+        "6" => Some(Key::KEY_6),
+        "7" => Some(Key::KEY_7),
+        "8" => Some(Key::KEY_8),
+        "9" => Some(Key::KEY_9),
+        "escape" => Some(Key::KEY_ESC),
+        "enter" | "return" => Some(Key::KEY_ENTER),
+        "space" => Some(Key::KEY_SPACE),
+        "tab" => Some(Key::KEY_TAB),
+        "up" => Some(Key::KEY_UP),
+        "down" => Some(Key::KEY_DOWN),
+        "left" => Some(Key::KEY_LEFT),
+        "right" => Some(Key::KEY_RIGHT),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn global_bindings_have_no_modes() {
+        let hotkeys = parse_contents("super + a\n    notify-send hi\n").unwrap();
+        assert_eq!(hotkeys.len(), 1);
+        assert!(hotkeys[0].modes.is_empty());
+        assert_eq!(hotkeys[0].command, "notify-send hi");
+    }
+
+    #[test]
+    fn bindings_after_mode_header_are_scoped() {
+        let hotkeys = parse_contents(
+            "mode:resize\nsuper + j\n    resize down\nmode:default\nsuper + k\n    resize up\n",
+        )
+        .unwrap();
+        assert_eq!(hotkeys[0].modes, HashSet::from(["resize".to_string()]));
+        assert_eq!(hotkeys[1].modes, HashSet::from(["default".to_string()]));
+    }
+
+    #[test]
+    fn directive_syntax_sets_enter_mode() {
+        let hotkeys = parse_contents("super + m\n    :mode resize\n").unwrap();
+        assert_eq!(hotkeys[0].enter_mode.as_deref(), Some("resize"));
+        assert!(!hotkeys[0].is_escape);
+    }
+
+    #[test]
+    fn directive_syntax_sets_escape() {
+        let hotkeys = parse_contents("super + shift + r\n    :escape\n").unwrap();
+        assert!(hotkeys[0].is_escape);
+        assert!(hotkeys[0].enter_mode.is_none());
+    }
+
+    #[test]
+    fn real_commands_named_like_directives_are_not_swallowed() {
+        let hotkeys = parse_contents("super + e\n    escape\nsuper + shift + m\n    mode my-script.sh\n")
+            .unwrap();
+        assert_eq!(hotkeys[0].command, "escape");
+        assert!(!hotkeys[0].is_escape);
+        assert_eq!(hotkeys[1].command, "mode my-script.sh");
+        assert!(hotkeys[1].enter_mode.is_none());
+    }
+
+    #[test]
+    fn unknown_directive_is_a_parse_error() {
+        assert!(parse_contents("super + a\n    :bogus\n").is_err());
+    }
+}
@@ -1,17 +1,22 @@
 use clap::{arg, Command};
 use evdev::{AttributeSet, AutoRepeat, Device, InputEventKind, Key};
+use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
 use nix::unistd::{Group, Uid};
 use signal_hook_tokio::Signals;
 use std::{
     collections::{HashMap, HashSet},
     env, fs,
     io::prelude::*,
-    os::unix::net::UnixStream,
+    os::unix::{fs::PermissionsExt, io::AsRawFd, net::UnixStream},
     path::Path,
     process::{exit, id},
+    sync::{Arc, Mutex},
 };
 use sysinfo::{ProcessExt, System, SystemExt};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::net::UnixListener;
 use tokio::select;
+use tokio::sync::mpsc;
 use tokio::time::Duration;
 use tokio::time::{sleep, Instant};
 use tokio_stream::{StreamExt, StreamMap};
@@ -24,6 +29,10 @@ mod uinput;
 #[cfg(test)]
 mod tests;
 
+const CONTROL_SOCKET_DIR: &str = "/run/swhkd";
+const CONTROL_SOCKET_PATH: &str = "/run/swhkd/control.sock";
+const DEFAULT_MODE: &str = "default";
+
 struct KeyboardState {
     state_modifiers: HashSet<config::Modifier>,
     state_keysyms: AttributeSet<evdev::Key>,
@@ -79,33 +88,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     permission_check();
 
-    let load_config = || {
-        let config_file_path: std::path::PathBuf = if args.is_present("config") {
-            Path::new(args.value_of("config").unwrap()).to_path_buf()
-        } else {
-            check_config_xdg()
-        };
-        log::debug!("Using config file path: {:#?}", config_file_path);
-
-        if !config_file_path.exists() {
-            log::error!("{:#?} doesn't exist", config_file_path);
-            exit(1);
-        }
-
-        let hotkeys = match config::load(config_file_path) {
-            Err(e) => {
-                log::error!("Config Error: {}", e);
-                exit(1);
-            }
-            Ok(out) => out,
-        };
-        for hotkey in &hotkeys {
-            log::debug!("hotkey: {:#?}", hotkey);
-        }
-        hotkeys
+    let config_file_path: std::path::PathBuf = if args.is_present("config") {
+        Path::new(args.value_of("config").unwrap()).to_path_buf()
+    } else {
+        check_config_xdg()
     };
+    log::debug!("Using config file path: {:#?}", config_file_path);
+
+    if !config_file_path.exists() {
+        log::error!("{:#?} doesn't exist", config_file_path);
+        exit(1);
+    }
 
-    let mut hotkeys = load_config();
+    let hotkeys = Arc::new(Mutex::new(load_hotkeys(&config_file_path)));
 
     log::trace!("Attempting to find all keyboard file descriptors.");
     let keyboard_devices: Vec<Device> = evdev::enumerate().filter(check_keyboard).collect();
@@ -143,23 +138,44 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         250
     };
 
-    fn send_command(hotkey: config::Hotkey) {
-        log::info!("Hotkey pressed: {:#?}", hotkey);
-        if let Err(e) = sock_send(&hotkey.command) {
-            log::error!("Failed to send command over IPC.");
-            log::error!("Is swhks running?");
-            log::error!("{:#?}", e)
+    let (command_tx, mut command_rx) = mpsc::channel::<config::Hotkey>(128);
+
+    tokio::spawn(async move {
+        while let Some(hotkey) = command_rx.recv().await {
+            log::info!("Hotkey pressed: {:#?}", hotkey);
+            match tokio::task::spawn_blocking(move || sock_send(&hotkey.command)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    log::error!("Failed to send command over IPC.");
+                    log::error!("Is swhks running?");
+                    log::error!("{:#?}", e)
+                }
+                Err(e) => {
+                    log::error!("Command socket task panicked: {:#?}", e);
+                }
+            }
         }
-    }
+    });
+
+    let send_command = move |hotkey: config::Hotkey| dispatch_command(&command_tx, hotkey);
 
     let mut signals = Signals::new(&[
         SIGUSR1, SIGUSR2, SIGHUP, SIGABRT, SIGBUS, SIGCHLD, SIGCONT, SIGINT, SIGPIPE, SIGQUIT,
         SIGSYS, SIGTERM, SIGTRAP, SIGTSTP, SIGVTALRM, SIGXCPU, SIGXFSZ,
     ])?;
-    let mut paused = false;
-    let mut temp_paused = false;
-
-    let mut last_hotkey: Option<config::Hotkey> = None;
+    let paused = Arc::new(Mutex::new(false));
+    let temp_paused = Arc::new(Mutex::new(false));
+    let last_hotkey: Arc<Mutex<Option<config::Hotkey>>> = Arc::new(Mutex::new(None));
+    let mode_state = Arc::new(Mutex::new(String::from(DEFAULT_MODE)));
+
+    tokio::spawn(run_control_socket(ControlState {
+        paused: Arc::clone(&paused),
+        temp_paused: Arc::clone(&temp_paused),
+        last_hotkey: Arc::clone(&last_hotkey),
+        hotkeys: Arc::clone(&hotkeys),
+        mode: Arc::clone(&mode_state),
+        config_file_path: config_file_path.clone(),
+    }));
     let mut keyboard_states: Vec<KeyboardState> = Vec::new();
     let mut keyboard_stream_map = StreamMap::new();
 
@@ -176,32 +192,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     loop {
         select! {
-            _ = &mut hotkey_repeat_timer, if &last_hotkey.is_some() => {
-                let hotkey = last_hotkey.clone().unwrap();
+            _ = &mut hotkey_repeat_timer, if last_hotkey.lock().unwrap().is_some() => {
+                let hotkey = last_hotkey.lock().unwrap().clone().unwrap();
                 send_command(hotkey.clone());
                 hotkey_repeat_timer.as_mut().reset(Instant::now() + Duration::from_millis(repeat_cooldown_duration));
             }
             Some(signal) = signals.next() => {
                 match signal {
                     SIGUSR1 => {
-                        paused = true;
-                        let keyboard_devices = evdev::enumerate().filter(check_keyboard);
-                        for mut device in keyboard_devices {
-                            let _ = &device.ungrab();
-                        };
+                        set_paused(&paused, true);
                     }
                     SIGUSR2 => {
-                        paused = false;
-                        let keyboard_devices = evdev::enumerate().filter(check_keyboard);
-                        for mut device in keyboard_devices {
-                            let _ = &device.grab();
-                        };
+                        set_paused(&paused, false);
                     }
                     SIGHUP => {
-                        hotkeys = load_config();
+                        *hotkeys.lock().unwrap() = load_hotkeys(&config_file_path);
                     }
                     SIGINT => {
-                        temp_paused = true;
+                        *temp_paused.lock().unwrap() = true;
                     }
                     _ => {
                         let keyboard_devices = evdev::enumerate().filter(check_keyboard);
@@ -225,17 +233,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         }
                     }
                     0 => {
+                        let mut last_hotkey_guard = last_hotkey.lock().unwrap();
                         if let Some(modifier) = modifiers_map.get(&key) {
-                            if let Some(hotkey) = &last_hotkey {
+                            if let Some(hotkey) = &*last_hotkey_guard {
                                 if hotkey.modifiers.contains(modifier) {
-                                    last_hotkey = None;
+                                    *last_hotkey_guard = None;
                                 }
                             }
                             keyboard_state.state_modifiers.remove(modifier);
                         } else if keyboard_state.state_keysyms.contains(key) {
-                            if let Some(hotkey) = &last_hotkey {
+                            if let Some(hotkey) = &*last_hotkey_guard {
                                 if key == hotkey.keysym {
-                                    last_hotkey = None;
+                                    *last_hotkey_guard = None;
                                 }
                             }
                             keyboard_state.state_keysyms.remove(key);
@@ -244,25 +253,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     _ => {}
                 }
 
-                let possible_hotkeys: Vec<&config::Hotkey> = hotkeys.iter()
+                let current_mode = mode_state.lock().unwrap().clone();
+
+                let hotkeys_guard = hotkeys.lock().unwrap();
+
+                let possible_hotkeys: Vec<config::Hotkey> = hotkeys_guard.iter()
                     .filter(|hotkey| hotkey.modifiers.len() == keyboard_state.state_modifiers.len())
+                    .filter(|hotkey| hotkey_active_in_mode(hotkey, &current_mode))
+                    .cloned()
                     .collect();
 
-                let event_in_hotkeys = hotkeys.iter().any(|hotkey| {
+                let event_in_hotkeys = hotkeys_guard.iter().any(|hotkey| {
                     hotkey.keysym.code() == event.code() &&
                     keyboard_state.state_modifiers
                         .iter()
                         .all(|x| hotkey.modifiers.contains(x)) &&
-                    keyboard_state.state_modifiers.len() == hotkey.modifiers.len()
+                    keyboard_state.state_modifiers.len() == hotkey.modifiers.len() &&
+                    hotkey_active_in_mode(hotkey, &current_mode)
                         });
 
+                drop(hotkeys_guard);
 
                 // Don't emit event to virtual device if it's from a valid hotkey
                 if !event_in_hotkeys {
                     uinput_device.emit(&[event]).unwrap();
                 }
 
-                if paused || last_hotkey.is_some() {
+                if *paused.lock().unwrap() || last_hotkey.lock().unwrap().is_some() {
                     continue;
                 }
 
@@ -273,12 +290,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 log::debug!("state_modifiers: {:#?}", keyboard_state.state_modifiers);
                 log::debug!("state_keysyms: {:#?}", keyboard_state.state_keysyms);
                 log::debug!("hotkey: {:#?}", possible_hotkeys);
-                if temp_paused {
+                if *temp_paused.lock().unwrap() {
                     if keyboard_state.state_modifiers.iter().all(|x| {
                         vec![config::Modifier::Shift, config::Modifier::Super].contains(x)
                     }) && keyboard_state.state_keysyms.contains(evdev::Key::KEY_ESC)
                     {
-                        temp_paused = false;
+                        *temp_paused.lock().unwrap() = false;
                     }
                     continue;
                 }
@@ -289,9 +306,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         && keyboard_state.state_modifiers.len() == hotkey.modifiers.len()
                         && keyboard_state.state_keysyms.contains(hotkey.keysym)
                     {
-                        last_hotkey = Some(hotkey.clone());
-                        send_command(hotkey.clone());
-                        hotkey_repeat_timer.as_mut().reset(Instant::now() + Duration::from_millis(repeat_cooldown_duration));
+                        if let Some(enter_mode) = &hotkey.enter_mode {
+                            log::info!("Mode switch: {} -> {}", current_mode, enter_mode);
+                            *mode_state.lock().unwrap() = enter_mode.clone();
+                        } else if hotkey.is_escape {
+                            log::info!("Mode switch: {} -> {}", current_mode, DEFAULT_MODE);
+                            *mode_state.lock().unwrap() = String::from(DEFAULT_MODE);
+                        } else {
+                            *last_hotkey.lock().unwrap() = Some(hotkey.clone());
+                            send_command(hotkey.clone());
+                            hotkey_repeat_timer.as_mut().reset(Instant::now() + Duration::from_millis(repeat_cooldown_duration));
+                        }
                         break;
                     }
                 }
@@ -320,6 +345,22 @@ pub fn permission_check() {
     }
 }
 
+fn hotkey_active_in_mode(hotkey: &config::Hotkey, current_mode: &str) -> bool {
+    hotkey.modes.is_empty() || hotkey.modes.contains(current_mode)
+}
+
+fn set_paused(paused: &Arc<Mutex<bool>>, value: bool) {
+    *paused.lock().unwrap() = value;
+    let keyboard_devices = evdev::enumerate().filter(check_keyboard);
+    for mut device in keyboard_devices {
+        if value {
+            let _ = device.ungrab();
+        } else {
+            let _ = device.grab();
+        }
+    }
+}
+
 pub fn check_keyboard(device: &Device) -> bool {
     if device.supported_keys().map_or(false, |keys| keys.contains(Key::KEY_ENTER)) {
         if device.name() == Some("swhkd virtual output") {
@@ -354,6 +395,20 @@ pub fn set_flags() -> Command<'static> {
     app
 }
 
+fn load_hotkeys(config_file_path: &Path) -> Vec<config::Hotkey> {
+    let hotkeys = match config::load(config_file_path.to_path_buf()) {
+        Err(e) => {
+            log::error!("Config Error: {}", e);
+            exit(1);
+        }
+        Ok(out) => out,
+    };
+    for hotkey in &hotkeys {
+        log::debug!("hotkey: {:#?}", hotkey);
+    }
+    hotkeys
+}
+
 pub fn check_config_xdg() -> std::path::PathBuf {
     let config_file_path: std::path::PathBuf = match env::var("XDG_CONFIG_HOME") {
         Ok(val) => {
@@ -373,3 +428,139 @@ fn sock_send(command: &str) -> std::io::Result<()> {
     stream.write_all(command.as_bytes())?;
     Ok(())
 }
+
+fn dispatch_command(command_tx: &mpsc::Sender<config::Hotkey>, hotkey: config::Hotkey) {
+    match command_tx.try_send(hotkey) {
+        Ok(_) => {}
+        Err(mpsc::error::TrySendError::Full(hotkey)) => {
+            log::warn!("Command channel is full, dropped hotkey: {:#?}", hotkey);
+        }
+        Err(mpsc::error::TrySendError::Closed(hotkey)) => {
+            log::error!("Command worker is gone, dropped hotkey: {:#?}", hotkey);
+        }
+    }
+}
+
+struct ControlState {
+    paused: Arc<Mutex<bool>>,
+    temp_paused: Arc<Mutex<bool>>,
+    last_hotkey: Arc<Mutex<Option<config::Hotkey>>>,
+    hotkeys: Arc<Mutex<Vec<config::Hotkey>>>,
+    mode: Arc<Mutex<String>>,
+    config_file_path: std::path::PathBuf,
+}
+
+async fn run_control_socket(state: ControlState) {
+    // /run/swhkd is root-only, so nothing else can pre-plant a symlink at
+    // CONTROL_SOCKET_PATH before we bind it.
+    if let Err(e) = fs::create_dir_all(CONTROL_SOCKET_DIR) {
+        log::error!("Failed to create {}: {:#?}", CONTROL_SOCKET_DIR, e);
+        return;
+    }
+    if let Err(e) = fs::set_permissions(CONTROL_SOCKET_DIR, fs::Permissions::from_mode(0o700)) {
+        log::error!("Failed to set permissions on {}: {:#?}", CONTROL_SOCKET_DIR, e);
+        return;
+    }
+
+    let _ = fs::remove_file(CONTROL_SOCKET_PATH);
+    let listener = match UnixListener::bind(CONTROL_SOCKET_PATH) {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("Failed to bind control socket at {}: {:#?}", CONTROL_SOCKET_PATH, e);
+            return;
+        }
+    };
+    if let Err(e) = fs::set_permissions(CONTROL_SOCKET_PATH, fs::Permissions::from_mode(0o600)) {
+        log::error!("Failed to set permissions on {}: {:#?}", CONTROL_SOCKET_PATH, e);
+        return;
+    }
+    log::debug!("Listening for control commands on {}", CONTROL_SOCKET_PATH);
+
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::error!("Failed to accept control socket connection: {:#?}", e);
+                continue;
+            }
+        };
+
+        match getsockopt(stream.as_raw_fd(), PeerCredentials) {
+            Ok(peer) if Uid::from_raw(peer.uid()) == Uid::current() => {}
+            Ok(peer) => {
+                log::warn!("Control socket: rejected connection from uid {}", peer.uid());
+                continue;
+            }
+            Err(e) => {
+                log::error!("Control socket: failed to read peer credentials: {:#?}", e);
+                continue;
+            }
+        }
+
+        let paused = Arc::clone(&state.paused);
+        let temp_paused = Arc::clone(&state.temp_paused);
+        let last_hotkey = Arc::clone(&state.last_hotkey);
+        let hotkeys = Arc::clone(&state.hotkeys);
+        let mode = Arc::clone(&state.mode);
+        let config_file_path = state.config_file_path.clone();
+
+        tokio::spawn(async move {
+            let mut line = String::new();
+            let (reader_half, mut writer_half) = stream.into_split();
+            let mut reader = tokio::io::BufReader::new(reader_half);
+            if let Err(e) = reader.read_line(&mut line).await {
+                log::error!("Failed to read from control socket: {:#?}", e);
+                return;
+            }
+
+            let mut parts = line.trim().splitn(2, ' ');
+            let command = parts.next().unwrap_or("");
+            let argument = parts.next();
+
+            match command {
+                "pause" => {
+                    set_paused(&paused, true);
+                    log::info!("Control socket: paused");
+                }
+                "resume" => {
+                    set_paused(&paused, false);
+                    log::info!("Control socket: resumed");
+                }
+                "reload" => {
+                    *hotkeys.lock().unwrap() = load_hotkeys(&config_file_path);
+                    log::info!("Control socket: reloaded config");
+                }
+                "mode" => {
+                    if let Some(new_mode) = argument.map(str::trim).filter(|m| !m.is_empty()) {
+                        let mut mode = mode.lock().unwrap();
+                        log::info!("Control socket: mode switch {} -> {}", mode, new_mode);
+                        *mode = new_mode.to_string();
+                    } else {
+                        log::warn!("Control socket: `mode` requires a mode name");
+                    }
+                }
+                "status" => {
+                    let status = format!(
+                        "paused={} temp_paused={} mode={} hotkeys={} last_hotkey={}\n",
+                        *paused.lock().unwrap(),
+                        *temp_paused.lock().unwrap(),
+                        *mode.lock().unwrap(),
+                        hotkeys.lock().unwrap().len(),
+                        last_hotkey
+                            .lock()
+                            .unwrap()
+                            .as_ref()
+                            .map_or_else(|| String::from("none"), |h| h.command.clone()),
+                    );
+                    if let Err(e) = writer_half.write_all(status.as_bytes()).await {
+                        log::error!("Failed to write control socket status: {:#?}", e);
+                    }
+                }
+                "" => {}
+                _ => {
+                    log::warn!("Control socket: unknown command {:#?}", command);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,44 @@
+use super::*;
+use std::collections::HashSet;
+
+fn test_hotkey(modes: &[&str]) -> config::Hotkey {
+    config::Hotkey {
+        keysym: Key::KEY_A,
+        modifiers: HashSet::new(),
+        command: String::from("true"),
+        modes: modes.iter().map(|m| m.to_string()).collect(),
+        enter_mode: None,
+        is_escape: false,
+    }
+}
+
+#[test]
+fn global_hotkey_is_active_in_every_mode() {
+    let hotkey = test_hotkey(&[]);
+    assert!(hotkey_active_in_mode(&hotkey, "default"));
+    assert!(hotkey_active_in_mode(&hotkey, "resize"));
+}
+
+#[test]
+fn scoped_hotkey_is_only_active_in_its_own_modes() {
+    let hotkey = test_hotkey(&["resize"]);
+    assert!(hotkey_active_in_mode(&hotkey, "resize"));
+    assert!(!hotkey_active_in_mode(&hotkey, "default"));
+}
+
+#[tokio::test]
+async fn dispatch_command_drops_when_channel_is_full() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<config::Hotkey>(1);
+    dispatch_command(&tx, test_hotkey(&[]));
+    dispatch_command(&tx, test_hotkey(&[]));
+
+    assert!(rx.recv().await.is_some());
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn dispatch_command_does_not_panic_when_receiver_is_gone() {
+    let (tx, rx) = tokio::sync::mpsc::channel::<config::Hotkey>(1);
+    drop(rx);
+    dispatch_command(&tx, test_hotkey(&[]));
+}